@@ -6,7 +6,7 @@
 //! ```rust
 //! use path_router::Tree;
 //! let mut routes = Tree::new();
-//! routes.add("GET/user/:username/profile", "profile.html");
+//! routes.add("GET/user/:username/profile", "profile.html").unwrap();
 //! assert_eq!(
 //!     routes.find("GET/user/my_name/profile"),
 //!     Some((&"profile.html", vec![("username", String::from("my_name"))])));
@@ -14,85 +14,238 @@
 
 #[macro_use] extern crate log;
 
-/// The routing information is stored as a trie.
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The error returned by [`Tree::add`] when a route cannot be added.
+#[derive(Debug, Eq, PartialEq)]
+pub enum AddError {
+    /// The exact route already has a value registered.
+    Duplicate,
+    /// The route conflicts with a catch-all segment at the same position,
+    /// in either order of registration: a value on the catch-all's parent
+    /// node always wins over the catch-all's empty-tail match, so that match
+    /// would be shadowed. Static and capture siblings
+    /// never trigger this: `find` always tries static branches, then the
+    /// capture branch, before falling back to a catch-all, so routes sharing
+    /// a position that way are all reachable.
+    Shadow,
+}
+
+impl fmt::Display for AddError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddError::Duplicate => write!(f, "duplicate route"),
+            AddError::Shadow => write!(f, "route conflicts with a catch-all segment at the same position"),
+        }
+    }
+}
+
+/// The error returned by [`Tree::build`] when a URL cannot be generated.
+#[derive(Debug, Eq, PartialEq)]
+pub enum BuildError {
+    /// The key was never registered via [`Tree::add`].
+    NotRegistered,
+    /// A capture required by the key was missing from the given params.
+    MissingParam(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::NotRegistered => write!(f, "route was never registered"),
+            BuildError::MissingParam(name) => write!(f, "missing value for capture \"{}\"", name),
+        }
+    }
+}
+
+/// The match returned by [`Tree::find_prefix`]: a value, its captured path
+/// segments, and any segments of the queried path left over beyond the
+/// matched ancestor (empty on an exact match).
+type PrefixMatch<'s, 'a, T> = (&'s T, Vec<(&'a str, String)>, Vec<String>);
+
+/// The closest matched ancestor found so far while descending in
+/// [`Tree::find_prefix_`]: its value, the path segments captured down to it,
+/// and how many segments of the query it consumed.
+type Fallback<'s, 'a, T> = (&'s (T, Vec<&'a str>), Vec<String>, usize);
+
+/// The routing information is stored as a radix trie: a chain of static
+/// segments with no branching is merged into a single node, in the spirit of
+/// the httprouter family of routers.
 ///
 /// # Description
 ///
-/// Each node is labelled with its path segment. The value is a tuple. The first
-/// element is generic, and is usually a handler function; the second element is
-/// the captured path segments.
+/// Each node is labelled with the path segment(s) it matches: a static node's
+/// `label` may hold several `/`-joined segments at once when nothing branches
+/// off them. The value is a tuple. The first element is generic, and is
+/// usually a handler function; the second element is the names of the
+/// captured path segments.
 ///
 pub struct Tree<'a, T> {
-    label: &'a str,
+    label: Cow<'a, str>,
+    /// True if this node is a catch-all (`*name`) node, which greedily
+    /// matches all remaining segments instead of just one.
+    is_catch_all: bool,
     value: Option<(T, Vec<&'a str>)>,
-    branches: Vec<Tree<'a, T>>
+    branches: Vec<Tree<'a, T>>,
+    /// First byte of each entry in `branches`, in the same order (0 for
+    /// capture/catch-all branches, whose label is always empty). Lets
+    /// `find_` skip a branch without splitting its label into segments.
+    indices: Vec<u8>,
+    /// Number of times this node has been the matched branch of a
+    /// successful `find` (a catch-all match in `find_prefix` also counts).
+    /// See [`Tree::optimize`]. An atomic (rather than a
+    /// `Cell`) so `Tree` stays `Sync`: `find` takes `&self` so it can be
+    /// shared read-only (e.g. behind an `Arc`) across worker threads.
+    hits: AtomicUsize,
+    /// Keys passed to `add`, stored as given and only in the root node,
+    /// indexed for `build` to look up in O(1) rather than searching the tree.
+    routes: HashSet<&'a str>
 }
 
 impl<'a, T> Tree<'a, T> {
     /// Constructs a new routing tree.
     pub fn new<'b>() -> Tree<'b, T> {
+        Tree::leaf_node(Cow::Borrowed(""), false)
+    }
+    fn leaf_node(label: Cow<'a, str>, is_catch_all: bool) -> Tree<'a, T> {
         Tree {
-            label: "",
+            label,
+            is_catch_all,
             value: None,
-            branches: Vec::new()
+            branches: Vec::new(),
+            indices: Vec::new(),
+            hits: AtomicUsize::new(0),
+            routes: HashSet::new()
+        }
+    }
+    fn push_branch(&mut self, branch: Tree<'a, T>) {
+        self.indices.push(first_byte(&branch));
+        self.branches.push(branch);
+    }
+    /// Splits the static branch at `idx` so that only its first `common`
+    /// segments remain on it, moving the rest of its label (and its value
+    /// and branches) down into a new single child.
+    fn split_branch(&mut self, idx: usize, common: usize) {
+        let prefix;
+        let suffix;
+        {
+            let label_segs: Vec<&str> = self.branches[idx].label.split('/').collect();
+            prefix = label_segs[..common].join("/");
+            suffix = label_segs[common..].join("/");
         }
+        let mut old_node = std::mem::replace(
+            &mut self.branches[idx],
+            Tree::leaf_node(Cow::Owned(prefix), false));
+        old_node.label = Cow::Owned(suffix);
+        old_node.hits.store(0, Ordering::Relaxed);
+        self.branches[idx].indices.push(first_byte(&old_node));
+        self.branches[idx].branches.push(old_node);
+        self.indices[idx] = first_byte(&self.branches[idx]);
     }
     /// Adds a new path and its associated value to the tree. Prefix a segment
-    /// with a colon (:) to enable capturing on the segment.
+    /// with a colon (:) to enable capturing on the segment, or with an
+    /// asterisk (*) to enable a catch-all capture of the remaining segments
+    /// (joined with `/`), including the empty tail.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AddError::Duplicate`] if the exact route already has a
+    /// value, or [`AddError::Shadow`] if the route ends at a node that has
+    /// (or would have) a catch-all child alongside a value of its own.
     ///
     /// # Panics
     ///
-    /// Panics if a duplicate route is added.
+    /// Panics if a catch-all segment is not the last segment of the key.
     ///
-    pub fn add(&mut self, key: &'a str, value: T) {
+    pub fn add(&mut self, key: &'a str, value: T) -> Result<(), AddError> {
         info!("Adding route {}", key);
-        let segments = key.split('/').filter(|s| !s.is_empty());
-        let capture_labels = Vec::new();    // Will be filled while iterating
-        self.add_(segments, value, capture_labels);
-    }
-    fn add_<I: Iterator<Item=&'a str>>(
-        &mut self, mut segments: I, value: T,
-        mut capture_labels: Vec<&'a str>) {
-        match segments.next() {
+        let segments: Vec<&str> = key.split('/').filter(|s| !s.is_empty()).collect();
+        let capture_labels = Vec::new();    // Will be filled while recursing
+        self.add_(&segments, value, capture_labels)?;
+        self.routes.insert(key);
+        Ok(())
+    }
+    fn add_(&mut self, segments: &[&'a str], value: T,
+            mut capture_labels: Vec<&'a str>) -> Result<(), AddError> {
+        let segment = match segments.first() {
             None => {
                 if self.value.is_some() {
                     error!("Duplicate route!");
-                    panic!("Duplicate route!");
+                    return Err(AddError::Duplicate);
                 }
-                self.value = Some((value, capture_labels))
+                // `find_` always checks `self.value` before falling back to a
+                // catch-all child (see `find_`), so a catch-all child already
+                // registered here would have its empty-tail match
+                // permanently shadowed by the value we're about to set.
+                if self.branches.iter().any(|t| t.is_catch_all) {
+                    error!("Shadowed by an existing catch-all route!");
+                    return Err(AddError::Shadow);
+                }
+                self.value = Some((value, capture_labels));
+                return Ok(());
             },
-            Some(segment) => {
-                if let Some(existing_branch) =
-                    self.branches.iter_mut().find(|t| t.label == segment) {
-                        existing_branch.add_(segments, value, capture_labels);
-                        return;
-                    }
-                if segment.starts_with(':') {
-                    capture_labels.push(&segment[1..]);
-                    if let Some(existing_branch) =
-                        self.branches.iter_mut().find(|t| t.label.is_empty()) {
-                            existing_branch.add_(
-                                segments, value, capture_labels);
-                            return;
-                        }
-                    let mut branch = Tree {
-                        label: "",
-                        value: None,
-                        branches: Vec::new()
-                    };
-                    branch.add_(segments, value, capture_labels);
-                    self.branches.push(branch);
-                } else {
-                    let mut branch = Tree {
-                        label: segment,
-                        value: None,
-                        branches: Vec::new()
-                    };
-                    branch.add_(segments, value, capture_labels);
-                    self.branches.push(branch);
+            Some(&segment) => segment
+        };
+        if let Some(stripped) = segment.strip_prefix('*') {
+            if segments.len() > 1 {
+                panic!("Catch-all segment must be the last segment!");
+            }
+            capture_labels.push(stripped);
+            if let Some(idx) = self.branches.iter().position(|t| t.is_catch_all) {
+                return self.branches[idx].add_(&segments[1..], value, capture_labels);
+            }
+            // A value already on this node would always win over the
+            // catch-all's empty-tail match (see `find_`), so the catch-all
+            // being added here would never be reachable for that case.
+            if self.value.is_some() {
+                error!("Shadowed by an existing route covering the same path!");
+                return Err(AddError::Shadow);
+            }
+            let mut branch = Tree::leaf_node(Cow::Borrowed(""), true);
+            branch.add_(&segments[1..], value, capture_labels)?;
+            self.push_branch(branch);
+            return Ok(());
+        }
+        if segment.starts_with(':') {
+            capture_labels.push(&segment[1..]);
+            if let Some(idx) = self.branches.iter()
+                .position(|t| !t.is_catch_all && t.label.is_empty()) {
+                    return self.branches[idx].add_(&segments[1..], value, capture_labels);
                 }
+            let mut branch = Tree::leaf_node(Cow::Borrowed(""), false);
+            branch.add_(&segments[1..], value, capture_labels)?;
+            self.push_branch(branch);
+            return Ok(());
+        }
+        let candidate = self.branches.iter().position(
+            |t| !t.is_catch_all && !t.label.is_empty()
+                && first_label_segment(&t.label) == segment);
+        if let Some(idx) = candidate {
+            let full_len = self.branches[idx].label.split('/').count();
+            let common = {
+                let label_segs: Vec<&str> = self.branches[idx].label.split('/').collect();
+                common_prefix_len(&label_segs, segments)
+            };
+            if common < full_len {
+                self.split_branch(idx, common);
             }
+            return self.branches[idx].add_(&segments[common..], value, capture_labels);
         }
+        let run_len = segments.iter()
+            .take_while(|s| !s.starts_with(':') && !s.starts_with('*'))
+            .count();
+        let label = if run_len == 1 {
+            Cow::Borrowed(segments[0])
+        } else {
+            Cow::Owned(segments[..run_len].join("/"))
+        };
+        let mut branch = Tree::leaf_node(label, false);
+        branch.add_(&segments[run_len..], value, capture_labels)?;
+        self.push_branch(branch);
+        Ok(())
     }
     /// Retrieve the value associated with the path, together with the captured
     /// path segments.
@@ -108,36 +261,421 @@ impl<'a, T> Tree<'a, T> {
     }
     fn find_(&self, segments: &[&str],
              captured: &mut Vec<String>) -> Option<&(T, Vec<&'a str>)> {
-        match segments.split_first() {
-            None => self.value.as_ref(),
-            Some((&segment, remaining)) => self.branches.iter().filter_map(|t| {
-                if t.label == segment {
-                    t.find_(remaining, captured)
-                } else if t.label == "" {
-                    captured.push(String::from(segment));
-                    let result = t.find_(remaining, captured);
-                    if result.is_none() {
-                        captured.pop();
+        if segments.is_empty() {
+            if self.value.is_some() {
+                return self.value.as_ref();
+            }
+            return self.find_catch_all(segments, captured);
+        }
+        let first = first_byte_of(segments[0]);
+        for (i, t) in self.branches.iter().enumerate() {
+            if t.is_catch_all || t.label.is_empty() || self.indices[i] != first {
+                continue;
+            }
+            let label_segs: Vec<&str> = t.label.split('/').collect();
+            let k = label_segs.len();
+            if segments.len() >= k && segments[..k] == label_segs[..] {
+                if let Some(result) = t.find_(&segments[k..], captured) {
+                    t.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(result);
+                }
+            }
+        }
+        if let Some(t) = self.branches.iter().find(|t| !t.is_catch_all && t.label.is_empty()) {
+            captured.push(String::from(segments[0]));
+            let result = t.find_(&segments[1..], captured);
+            if let Some(r) = result {
+                t.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(r);
+            }
+            captured.pop();
+        }
+        self.find_catch_all(segments, captured)
+    }
+    /// Tries the catch-all branch, if any, capturing the whole remaining
+    /// `segments` slice (including the empty tail) as a single value.
+    fn find_catch_all(&self, segments: &[&str],
+                       captured: &mut Vec<String>) -> Option<&(T, Vec<&'a str>)> {
+        let branch = self.branches.iter().find(|t| t.is_catch_all)?;
+        captured.push(segments.join("/"));
+        let result = branch.value.as_ref();
+        if result.is_none() {
+            captured.pop();
+        } else {
+            branch.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+    /// Returns a mutable reference to the value registered at `key`, for
+    /// in-place handler replacement. Uses the same traversal as
+    /// [`Tree::find`], including capture and catch-all segments.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut T> {
+        let segments: Vec<&str> = key.split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        self.get_mut_(segments.as_slice()).map(|&mut (ref mut v, _)| v)
+    }
+    fn get_mut_(&mut self, segments: &[&str]) -> Option<&mut (T, Vec<&'a str>)> {
+        if segments.is_empty() {
+            if self.value.is_some() {
+                return self.value.as_mut();
+            }
+            return self.branches.iter_mut()
+                .find(|t| t.is_catch_all)
+                .and_then(|t| t.value.as_mut());
+        }
+        // Put the capture branch behind every static one (a stable sort, so
+        // the static branches keep their order) to match `find`'s
+        // static-before-capture precedence in the list-order walk below.
+        self.branches.sort_by_key(|t| !t.is_catch_all && t.label.is_empty());
+        self.indices = self.branches.iter().map(first_byte).collect();
+        get_mut_branches(&mut self.branches, segments)
+    }
+    /// Removes the route registered under `key` (in the same `:name`/`*name`
+    /// syntax passed to [`Tree::add`]), returning its value if it was
+    /// registered. Branch nodes left with no value and no remaining
+    /// branches are pruned back up the chain; a node that still holds its
+    /// own value or other branches is left in place.
+    pub fn remove(&mut self, key: &str) -> Option<T> {
+        let segments: Vec<&str> = key.split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let removed = self.remove_(segments.as_slice());
+        if removed.is_some() {
+            // `key` may be formatted differently (extra/duplicate slashes)
+            // from the string originally passed to `add` while still
+            // matching the same segments, so look up the registered key by
+            // its canonical segments instead of by literal string equality.
+            let canonical = self.routes.iter().copied()
+                .find(|k| k.split('/').filter(|s| !s.is_empty()).eq(segments.iter().copied()));
+            if let Some(canonical) = canonical {
+                self.routes.remove(canonical);
+            }
+        }
+        removed
+    }
+    fn remove_(&mut self, segments: &[&str]) -> Option<T> {
+        let segment = match segments.first() {
+            None => return self.value.take().map(|(v, _)| v),
+            Some(&segment) => segment
+        };
+        let idx = if segment.starts_with('*') {
+            self.branches.iter().position(|t| t.is_catch_all)?
+        } else if segment.starts_with(':') {
+            self.branches.iter().position(|t| !t.is_catch_all && t.label.is_empty())?
+        } else {
+            let idx = self.branches.iter().position(
+                |t| !t.is_catch_all && !t.label.is_empty()
+                    && first_label_segment(&t.label) == segment)?;
+            let label_segs: Vec<&str> = self.branches[idx].label.split('/').collect();
+            let k = label_segs.len();
+            if segments.len() < k || segments[..k] != label_segs[..] {
+                return None;
+            }
+            idx
+        };
+        let consumed = if segment.starts_with('*') || segment.starts_with(':') {
+            1
+        } else {
+            self.branches[idx].label.split('/').count()
+        };
+        let removed = self.branches[idx].remove_(&segments[consumed..]);
+        if removed.is_some()
+            && self.branches[idx].value.is_none()
+            && self.branches[idx].branches.is_empty() {
+                self.branches.remove(idx);
+                self.indices.remove(idx);
+            }
+        removed
+    }
+    /// Returns every registered route's reconstructed key, paired with a
+    /// reference to its value. Useful for diagnostics, or for building a
+    /// reverse-routing index.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+        let mut pairs = Vec::new();
+        self.collect_values(&mut Vec::new(), &mut pairs);
+        pairs.into_iter()
+    }
+    fn collect_values<'s>(&'s self, prefix: &mut Vec<Segment<'s>>,
+                           out: &mut Vec<(String, &'s T)>) {
+        if let Some((value, labels)) = &self.value {
+            out.push((reconstruct_key(prefix, labels), value));
+        }
+        for branch in &self.branches {
+            prefix.push(if branch.is_catch_all {
+                Segment::CatchAll
+            } else if branch.label.is_empty() {
+                Segment::Capture
+            } else {
+                Segment::Static(branch.label.as_ref())
+            });
+            branch.collect_values(prefix, out);
+            prefix.pop();
+        }
+    }
+    /// Like [`Tree::find`], but when the path runs past the deepest
+    /// registered node without an exact match, falls back to the value of
+    /// the closest matched ancestor instead of returning `None`. The third
+    /// element of the returned tuple holds the segments of the path that
+    /// were left over beyond that ancestor (empty on an exact match). On a
+    /// tie in ancestor depth, a static ancestor wins over a capture one, to
+    /// stay consistent with `find`'s own static-before-capture precedence.
+    pub fn find_prefix(&self, key: &str) -> Option<PrefixMatch<'_, 'a, T>> {
+        let segments: Vec<&str> = key.split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut captured = Vec::new();
+        let mut fallback = None;
+        match self.find_prefix_(segments.as_slice(), &mut captured, &mut fallback, 0) {
+            Some((v, labels)) =>
+                Some((v, labels.iter().cloned().zip(captured).collect(), Vec::new())),
+            None => fallback.map(|((v, labels), captured, consumed)| {
+                (v, labels.iter().cloned().zip(captured).collect(),
+                 segments[consumed..].iter().map(|&s| String::from(s)).collect())
+            })
+        }
+    }
+    fn find_prefix_<'s>(&'s self, segments: &[&str], captured: &mut Vec<String>,
+                         fallback: &mut Option<Fallback<'s, 'a, T>>,
+                         consumed: usize) -> Option<&'s (T, Vec<&'a str>)> {
+        if let Some(value) = &self.value {
+            // Only overwrite the fallback with a strictly deeper one, never a
+            // same-depth or shallower one: the
+            // static branches below are tried before the capture branch, so
+            // whichever of them sets the fallback first at a given depth
+            // should keep winning that tie, matching `find`'s own
+            // static-before-capture precedence.
+            let is_deeper_or_new = fallback.as_ref()
+                .is_none_or(|(_, _, prev_consumed)| consumed > *prev_consumed);
+            if is_deeper_or_new {
+                *fallback = Some((value, captured.clone(), consumed));
+            }
+        }
+        if segments.is_empty() {
+            return self.value.as_ref().or_else(|| self.find_catch_all(segments, captured));
+        }
+        let first = first_byte_of(segments[0]);
+        for (i, t) in self.branches.iter().enumerate() {
+            if t.is_catch_all || t.label.is_empty() || self.indices[i] != first {
+                continue;
+            }
+            let label_segs: Vec<&str> = t.label.split('/').collect();
+            let k = label_segs.len();
+            if segments.len() >= k && segments[..k] == label_segs[..] {
+                if let Some(result) =
+                    t.find_prefix_(&segments[k..], captured, fallback, consumed + k) {
+                        return Some(result);
                     }
-                    result
-                } else {
-                    None
+            }
+        }
+        if let Some(t) = self.branches.iter().find(|t| !t.is_catch_all && t.label.is_empty()) {
+            captured.push(String::from(segments[0]));
+            let result = t.find_prefix_(&segments[1..], captured, fallback, consumed + 1);
+            if result.is_some() {
+                return result;
+            }
+            captured.pop();
+        }
+        self.find_catch_all(segments, captured)
+    }
+    /// Generates the concrete path for a previously registered route `key`,
+    /// substituting each `:name`/`*name` segment with the matching value in
+    /// `params`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::NotRegistered`] if `key` was never added, or
+    /// [`BuildError::MissingParam`] if a capture in `key` has no matching
+    /// entry in `params`.
+    pub fn build(&self, key: &str, params: &[(&str, &str)]) -> Result<String, BuildError> {
+        if !self.routes.contains(key) {
+            return Err(BuildError::NotRegistered);
+        }
+        let mut built = String::new();
+        for segment in key.split('/').filter(|s| !s.is_empty()) {
+            built.push('/');
+            if let Some(name) = segment.strip_prefix(':').or_else(|| segment.strip_prefix('*')) {
+                match params.iter().find(|&&(n, _)| n == name) {
+                    Some(&(_, value)) => built.push_str(value),
+                    None => return Err(BuildError::MissingParam(String::from(name)))
                 }
-            }).next()
+            } else {
+                built.push_str(segment);
+            }
+        }
+        Ok(built)
+    }
+    /// Suggests up to `max` registered route keys closest to `key`, ordered
+    /// by ascending Levenshtein edit distance. Useful for "did you mean?"
+    /// hints on 404 pages.
+    pub fn suggest(&self, key: &str, max: usize) -> Vec<(String, usize)> {
+        let mut keys = Vec::new();
+        self.collect_keys(&mut Vec::new(), &mut keys);
+        let mut suggestions: Vec<(String, usize)> = keys.into_iter()
+            .map(|candidate| {
+                let distance = levenshtein(key, &candidate);
+                (candidate, distance)
+            })
+            .collect();
+        suggestions.sort_by_key(|&(_, distance)| distance);
+        suggestions.truncate(max);
+        suggestions
+    }
+    /// Walks every node holding a value, appending its reconstructed full
+    /// route key (with capture segments re-prefixed with `:`/`*`) to `out`.
+    fn collect_keys<'s>(&'s self, prefix: &mut Vec<Segment<'s>>, out: &mut Vec<String>) {
+        if let Some((_, labels)) = &self.value {
+            out.push(reconstruct_key(prefix, labels));
+        }
+        for branch in &self.branches {
+            prefix.push(if branch.is_catch_all {
+                Segment::CatchAll
+            } else if branch.label.is_empty() {
+                Segment::Capture
+            } else {
+                Segment::Static(branch.label.as_ref())
+            });
+            branch.collect_keys(prefix, out);
+            prefix.pop();
+        }
+    }
+    /// Reorders the branches of this node, and of every descendant, so that
+    /// the branch with the most recorded [`Tree::find`] hits comes first.
+    /// `find` still tries every static branch before the capture branch, and
+    /// that before the catch-all, so only the order of static branches
+    /// changes which one is tried first.
+    ///
+    /// `find` only takes `&self`, so it cannot itself bubble a hot branch to
+    /// the front while routing; call `optimize` periodically (e.g. from a
+    /// maintenance task) to apply the accumulated hit counts.
+    pub fn optimize(&mut self) {
+        self.branches.sort_by_key(|b| std::cmp::Reverse(b.hits.load(Ordering::Relaxed)));
+        self.indices = self.branches.iter().map(first_byte).collect();
+        for branch in &mut self.branches {
+            branch.optimize();
+        }
+    }
+}
+
+/// First byte of a static branch's label, or 0 for capture/catch-all
+/// branches (whose label is always empty).
+fn first_byte<'a, T>(t: &Tree<'a, T>) -> u8 {
+    if t.is_catch_all || t.label.is_empty() {
+        0
+    } else {
+        t.label.as_bytes()[0]
+    }
+}
+
+fn first_byte_of(segment: &str) -> u8 {
+    segment.as_bytes().first().copied().unwrap_or(0)
+}
+
+/// Mutable counterpart of [`Tree::find_`]'s branch scan, used by
+/// [`Tree::get_mut`]. Written as a recursive slice walk (rather than a loop
+/// over `branches`) so that only one branch's `&mut` borrow is ever live at
+/// a time, which is what lets it backtrack from a branch whose own lookup
+/// fails to the next one. Branches are tried in list order, which
+/// `get_mut_` arranges with the static branches ahead of the capture
+/// branch. A catch-all branch defers to the rest of the
+/// list before falling back to itself, so it is always tried last
+/// regardless of where it sits among its siblings.
+fn get_mut_branches<'t, 'a, T>(
+    branches: &'t mut [Tree<'a, T>], segments: &[&str]) -> Option<&'t mut (T, Vec<&'a str>)> {
+    let (first, rest) = branches.split_first_mut()?;
+    if first.is_catch_all {
+        if let Some(result) = get_mut_branches(rest, segments) {
+            return Some(result);
+        }
+        return first.value.as_mut();
+    }
+    if first.label.is_empty() {
+        if let Some(result) = first.get_mut_(&segments[1..]) {
+            return Some(result);
+        }
+    } else {
+        let label_segs: Vec<&str> = first.label.split('/').collect();
+        let k = label_segs.len();
+        if segments.len() >= k && segments[..k] == label_segs[..] {
+            if let Some(result) = first.get_mut_(&segments[k..]) {
+                return Some(result);
+            }
+        }
+    }
+    get_mut_branches(rest, segments)
+}
+
+/// The first `/`-delimited segment of a (possibly radix-compressed) label.
+fn first_label_segment(label: &str) -> &str {
+    match label.find('/') {
+        Some(pos) => &label[..pos],
+        None => label
+    }
+}
+
+/// Number of leading elements that `a` and `b` have in common.
+fn common_prefix_len(a: &[&str], b: &[&str]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// A reconstructed route segment, as seen while walking the tree.
+enum Segment<'a> {
+    Static(&'a str),
+    Capture,
+    CatchAll
+}
+
+/// Rebuilds the original `key` syntax (e.g. `:name`, `*name`) for a route
+/// from its walked `prefix` and the capture names stored on its leaf value.
+fn reconstruct_key(prefix: &[Segment], labels: &[&str]) -> String {
+    let mut labels = labels.iter();
+    let mut key = String::new();
+    for segment in prefix {
+        key.push('/');
+        match segment {
+            Segment::Static(label) => key.push_str(label),
+            Segment::Capture => {
+                key.push(':');
+                key.push_str(labels.next().expect("capture label for capture segment"));
+            },
+            Segment::CatchAll => {
+                key.push('*');
+                key.push_str(labels.next().expect("capture label for catch-all segment"));
+            }
+        }
+    }
+    key
+}
+
+/// Computes the Levenshtein edit distance between `a` and `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &a_char) in a.iter().enumerate() {
+        let mut cur_row = vec![0; b.len() + 1];
+        cur_row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char != b_char { 1 } else { 0 };
+            cur_row[j + 1] = std::cmp::min(
+                std::cmp::min(cur_row[j] + 1, prev_row[j + 1] + 1),
+                prev_row[j] + substitution_cost);
         }
+        prev_row = cur_row;
     }
+    prev_row[b.len()]
 }
 
 #[cfg(test)]
 mod tests {
-    use Tree;
+    use {AddError, BuildError, Tree};
     #[test]
     fn can_add_and_find() {
         let mut routes = Tree::new();
-        routes.add("/", 0);
-        routes.add("/var", 1);
-        routes.add("/var/www", 11);
-        routes.add("/var/log", 12);
+        routes.add("/", 0).unwrap();
+        routes.add("/var", 1).unwrap();
+        routes.add("/var/www", 11).unwrap();
+        routes.add("/var/log", 12).unwrap();
         assert_eq!(routes.find("/vax"), None);
         assert_eq!(routes.find("/var/something"), None);
         assert_eq!(
@@ -156,9 +694,9 @@ mod tests {
     #[test]
     fn can_add_and_capture_and_find() {
         let mut routes = Tree::new();
-        routes.add("/user/:username", 11);
-        routes.add("/user/:username/:intent/show", 111);
-        routes.add("/user/:username/profile", 112);
+        routes.add("/user/:username", 11).unwrap();
+        routes.add("/user/:username/:intent/show", 111).unwrap();
+        routes.add("/user/:username/profile", 112).unwrap();
         assert_eq!(routes.find("/user/myname/delete"), None);
         assert_eq!(routes.find("/user/myname/cook/throw"), None);
         assert_eq!(
@@ -175,6 +713,33 @@ mod tests {
             ])));
     }
     #[test]
+    fn can_add_and_find_catch_all() {
+        let mut routes = Tree::new();
+        routes.add("/static/*filepath", 1).unwrap();
+        routes.add("/user/:username/profile", 2).unwrap();
+        assert_eq!(
+            routes.find("/static"),
+            Some((&1, vec![("filepath", String::from(""))])));
+        assert_eq!(
+            routes.find("/static/"),
+            Some((&1, vec![("filepath", String::from(""))])));
+        assert_eq!(
+            routes.find("/static/app.css"),
+            Some((&1, vec![("filepath", String::from("app.css"))])));
+        assert_eq!(
+            routes.find("/static/css/app.css"),
+            Some((&1, vec![("filepath", String::from("css/app.css"))])));
+        assert_eq!(
+            routes.find("/user/myname/profile"),
+            Some((&2, vec![("username", String::from("myname"))])));
+    }
+    #[test]
+    #[should_panic]
+    fn catch_all_must_be_last_segment() {
+        let mut routes = Tree::new();
+        let _ = routes.add("/static/*filepath/more", 1);
+    }
+    #[test]
     fn can_add_and_capture_and_find_handlers() {
         let mut routes = Tree::new();
         let handler = |captured: Vec<(&str, String)>| {
@@ -184,10 +749,207 @@ mod tests {
             assert_eq!(captured[1].0, "file");
             assert_eq!(captured[1].1, "myfile");
         };
-        routes.add("home/:folder/:file", handler);
+        routes.add("home/:folder/:file", handler).unwrap();
         match routes.find("/home/myfolder/myfile") {
             None => assert!(false),
             Some((fx, captured)) => fx(captured)
         }
     }
+    #[test]
+    fn can_find_prefix() {
+        let mut routes = Tree::new();
+        routes.add("/trans/rights", 1).unwrap();
+        routes.add("/trans/:id/edit", 2).unwrap();
+        assert_eq!(
+            routes.find_prefix("/trans/rights"),
+            Some((&1, vec![], vec![])));
+        assert_eq!(
+            routes.find_prefix("/trans/rights/now"),
+            Some((&1, vec![], vec![String::from("now")])));
+        assert_eq!(
+            routes.find_prefix("/trans/rights/now/and/forever"),
+            Some((&1, vec![], vec![
+                  String::from("now"), String::from("and"), String::from("forever")
+            ])));
+        assert_eq!(
+            routes.find_prefix("/trans/42/edit"),
+            Some((&2, vec![("id", String::from("42"))], vec![])));
+        assert_eq!(routes.find_prefix("/other"), None);
+    }
+    #[test]
+    fn find_prefix_prefers_static_ancestor_on_depth_tie() {
+        let mut routes = Tree::new();
+        routes.add("/a/b", 1).unwrap();
+        routes.add("/a/:id", 2).unwrap();
+        // Both "/a/b" (static) and "/a/:id" (capture) are equally-deep
+        // ancestors of "/a/b/extra"; the static one must win, matching
+        // `find`'s own static-before-capture precedence.
+        assert_eq!(
+            routes.find_prefix("/a/b/extra"),
+            Some((&1, vec![], vec![String::from("extra")])));
+    }
+    #[test]
+    fn can_build_url_from_key_and_params() {
+        let mut routes = Tree::new();
+        routes.add("/user/:username/profile", 1).unwrap();
+        assert_eq!(
+            routes.build("/user/:username/profile", &[("username", "alice")]),
+            Ok(String::from("/user/alice/profile")));
+        assert_eq!(
+            routes.build("/user/:username/profile", &[]),
+            Err(BuildError::MissingParam(String::from("username"))));
+        assert_eq!(
+            routes.build("/user/:username", &[("username", "alice")]),
+            Err(BuildError::NotRegistered));
+    }
+    #[test]
+    fn can_suggest_closest_routes() {
+        let mut routes: Tree<i32> = Tree::new();
+        routes.add("/user/:username/profile", 1).unwrap();
+        routes.add("/user/:username/settings", 2).unwrap();
+        routes.add("/static/*filepath", 3).unwrap();
+        let suggestions = routes.suggest("/user/:username/profilee", 2);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0], (String::from("/user/:username/profile"), 1));
+    }
+    #[test]
+    fn add_returns_duplicate_error() {
+        let mut routes = Tree::new();
+        routes.add("/var/www", 1).unwrap();
+        assert_eq!(routes.add("/var/www", 2), Err(AddError::Duplicate));
+    }
+    #[test]
+    fn add_allows_static_and_capture_sharing_a_position() {
+        // `find` always tries static branches before the capture branch, so
+        // neither registration order makes either route unreachable.
+        let mut routes = Tree::new();
+        routes.add("/user/:username", 1).unwrap();
+        routes.add("/user/profile", 2).unwrap();
+        assert_eq!(routes.find("/user/profile"), Some((&2, vec![])));
+        assert_eq!(routes.find("/user/bob"),
+                   Some((&1, vec![("username", String::from("bob"))])));
+
+        let mut routes = Tree::new();
+        routes.add("/user/profile", 1).unwrap();
+        routes.add("/user/:username", 2).unwrap();
+        assert_eq!(routes.find("/user/profile"), Some((&1, vec![])));
+        assert_eq!(routes.find("/user/bob"),
+                   Some((&2, vec![("username", String::from("bob"))])));
+    }
+    #[test]
+    fn add_returns_shadow_error_for_catch_all_conflicts() {
+        let mut routes = Tree::new();
+        routes.add("/static", 1).unwrap();
+        assert_eq!(routes.add("/static/*filepath", 2), Err(AddError::Shadow));
+
+        let mut routes = Tree::new();
+        routes.add("/static/*filepath", 1).unwrap();
+        assert_eq!(routes.add("/static", 2), Err(AddError::Shadow));
+    }
+    #[test]
+    fn radix_compression_splits_on_divergent_routes() {
+        let mut routes = Tree::new();
+        routes.add("/api/v1/users/list", 1).unwrap();
+        routes.add("/api/v1/users/create", 2).unwrap();
+        routes.add("/api/v1/groups", 3).unwrap();
+        assert_eq!(routes.find("/api/v1/users/list"), Some((&1, vec![])));
+        assert_eq!(routes.find("/api/v1/users/create"), Some((&2, vec![])));
+        assert_eq!(routes.find("/api/v1/groups"), Some((&3, vec![])));
+        assert_eq!(routes.find("/api/v1/users"), None);
+        assert_eq!(routes.find("/api/v2/users/list"), None);
+    }
+    #[test]
+    fn optimize_preserves_routing_after_reordering() {
+        let mut routes = Tree::new();
+        routes.add("/a", 1).unwrap();
+        routes.add("/b", 2).unwrap();
+        routes.add("/c", 3).unwrap();
+        assert_eq!(routes.find("/c"), Some((&3, vec![])));
+        assert_eq!(routes.find("/c"), Some((&3, vec![])));
+        assert_eq!(routes.find("/c"), Some((&3, vec![])));
+        routes.optimize();
+        assert_eq!(routes.find("/a"), Some((&1, vec![])));
+        assert_eq!(routes.find("/b"), Some((&2, vec![])));
+        assert_eq!(routes.find("/c"), Some((&3, vec![])));
+    }
+    #[test]
+    fn optimize_preserves_precedence_over_capture_and_catch_all() {
+        let mut routes = Tree::new();
+        routes.add("/users/new", 1).unwrap();
+        routes.add("/users/:id", 2).unwrap();
+        routes.add("/users/*rest", 3).unwrap();
+        // Drive up the capture and catch-all branches' hit counts so
+        // `optimize` would put them ahead of the static branch, then check
+        // that static-before-capture-before-catch-all precedence still
+        // holds afterwards.
+        assert_eq!(routes.find("/users/bob"),
+                   Some((&2, vec![("id", String::from("bob"))])));
+        assert_eq!(routes.find("/users/a/b"),
+                   Some((&3, vec![("rest", String::from("a/b"))])));
+        assert_eq!(routes.find("/users/a/b"),
+                   Some((&3, vec![("rest", String::from("a/b"))])));
+        routes.optimize();
+        assert_eq!(routes.find("/users/new"), Some((&1, vec![])));
+        assert_eq!(routes.find("/users/bob"),
+                   Some((&2, vec![("id", String::from("bob"))])));
+        assert_eq!(routes.find("/users/a/b"),
+                   Some((&3, vec![("rest", String::from("a/b"))])));
+    }
+    #[test]
+    fn tree_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Tree<fn()>>();
+    }
+    #[test]
+    fn can_remove_and_prune_empty_branches() {
+        let mut routes = Tree::new();
+        routes.add("/api/v1/users/list", 1).unwrap();
+        routes.add("/api/v1/users/create", 2).unwrap();
+        routes.add("/api/v1/groups", 3).unwrap();
+        assert_eq!(routes.remove("/api/v1/users/list"), Some(1));
+        assert_eq!(routes.find("/api/v1/users/list"), None);
+        assert_eq!(routes.find("/api/v1/users/create"), Some((&2, vec![])));
+        assert_eq!(routes.find("/api/v1/groups"), Some((&3, vec![])));
+        assert_eq!(routes.remove("/api/v1/users/list"), None);
+        assert_eq!(
+            routes.build("/api/v1/users/list", &[]),
+            Err(BuildError::NotRegistered));
+
+        assert_eq!(routes.remove("/api/v1/users/create"), Some(2));
+        assert_eq!(routes.find("/api/v1/users/create"), None);
+        assert_eq!(routes.find("/api/v1/groups"), Some((&3, vec![])));
+    }
+    #[test]
+    fn remove_keeps_the_routes_index_in_sync_with_an_equivalent_key() {
+        let mut routes = Tree::new();
+        routes.add("/var/www", 1).unwrap();
+        assert_eq!(routes.remove("//var//www//"), Some(1));
+        assert_eq!(
+            routes.build("/var/www", &[]),
+            Err(BuildError::NotRegistered));
+    }
+    #[test]
+    fn can_get_mut_and_replace_handler_in_place() {
+        let mut routes = Tree::new();
+        routes.add("/user/:username/profile", 1).unwrap();
+        *routes.get_mut("/user/alice/profile").unwrap() = 2;
+        assert_eq!(
+            routes.find("/user/alice/profile"),
+            Some((&2, vec![("username", String::from("alice"))])));
+        assert_eq!(routes.get_mut("/user/alice/settings"), None);
+    }
+    #[test]
+    fn can_iterate_over_all_registered_routes() {
+        let mut routes = Tree::new();
+        routes.add("/user/:username/profile", 1).unwrap();
+        routes.add("/static/*filepath", 2).unwrap();
+        let mut keys: Vec<(String, i32)> = routes.iter()
+            .map(|(key, &value)| (key, value))
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec![
+            (String::from("/static/*filepath"), 2),
+            (String::from("/user/:username/profile"), 1)
+        ]);
+    }
 }